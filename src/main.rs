@@ -1,301 +1,574 @@
-use anyhow::{anyhow, bail, Context, Result};
-use async_recursion::async_recursion;
+#[cfg(test)]
+mod memory_store;
+mod sqlite_store;
+mod store;
+mod types;
+
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Local};
 use clap::Parser;
-use sqlx::{Acquire, SqlitePool};
-use std::{path::PathBuf, time::SystemTime};
+use futures::{stream, StreamExt};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use sqlite_store::SqliteStore;
+use store::{FileSnapshot, Store};
+use types::{
+    datetime_local_to_system_time, parse_local_datetime, system_time_to_datetime_local,
+    LogActionType,
+};
+
+/// How many files to hash/stat concurrently via `spawn_blocking` while
+/// scanning a directory tree.
+const SCAN_WORKER_COUNT: usize = 8;
 
 #[derive(Parser)]
 enum Commands {
     Apply {
         path: String,
+        /// Restore the snapshot closest to (at or before) this local
+        /// datetime, e.g. "2024-01-02 15:04:05", instead of the latest one.
+        #[arg(long = "at")]
+        at: Option<String>,
+        /// Restore a specific snapshot by the id shown by `list`, instead of
+        /// the latest one.
+        #[arg(long = "snapshot")]
+        snapshot: Option<i64>,
+        /// Report what would change without touching any file.
+        #[arg(long = "dry-run")]
+        dry_run: bool,
     },
     Save {
         path: String,
         #[arg(short = 'r', long = "recursive")]
         recursive: bool,
     },
+    List {
+        path: String,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let database_file_name = dotenvy::var("DATABASE_PATH")?;
     let pool = SqlitePool::connect(&format!("file:{}", database_file_name)).await?;
+    let store = SqliteStore::new(pool);
 
     match Commands::parse() {
-        Commands::Apply { path } => apply_dirs_props(&pool, path.into()).await,
-        Commands::Save { path, recursive } => {
-            if recursive {
-                save_dirs_props_recursive(&pool, path.into()).await
-            } else {
-                save_dirs_props(&pool, path.into()).await
-            }
-        }
-    }
-}
-
-#[async_recursion]
-async fn save_dirs_props_recursive(pool: &SqlitePool, dir: PathBuf) -> Result<()> {
-    save_dirs_props(pool, dir.clone()).await?;
-    for entry in dir.read_dir()? {
-        let entry = entry?;
-        let file_type = entry.file_type()?;
-        if file_type.is_dir() {
-            save_dirs_props_recursive(pool, entry.path()).await?;
-        }
+        Commands::Apply {
+            path,
+            at,
+            snapshot,
+            dry_run,
+        } => apply_dirs_props(&store, path.into(), at, snapshot, dry_run).await,
+        Commands::Save { path, recursive } => save_dirs_props(&store, path.into(), recursive).await,
+        Commands::List { path } => list_dir_snapshots(&store, path.into()).await,
     }
-    Ok(())
 }
 
-async fn apply_dirs_props(pool: &SqlitePool, dir: PathBuf) -> Result<()> {
-    if !dir.exists() {
+async fn apply_dirs_props(
+    store: &impl Store,
+    dir: PathBuf,
+    at: Option<String>,
+    snapshot: Option<i64>,
+    dry_run: bool,
+) -> Result<()> {
+    if !dir_exists(&dir).await {
         bail!("Doesn't exist dir: {:?}", dir);
     }
-    let dir_prop_row_id: i64 = get_dir_prop_row_id(pool, dir.clone())
+    let dir_prop_row_id = store
+        .resolve_dir_id(&dir)
         .await
         .context(format!("Failed to get dir_prop_row_id: {:?}", dir))?;
 
-    let mut tx = pool.begin().await?;
-    let conn = tx.acquire().await?;
-
-    let latest_cached_time: DateTime<Local> = match sqlx::query_as::<_, (DateTime<Local>,)>(
-        "
-            SELECT cached_date FROM dir_actions_log
-            WHERE dir_id = $1 AND action_type = $2
-            ORDER BY cached_date DESC
-            LIMIT 1
-        ",
-    )
-    .bind(dir_prop_row_id)
-    .bind(LogActionType::CacheDates)
-    .fetch_one(&mut *conn)
-    .await
-    {
-        Ok((cached_date,)) => cached_date,
-        Err(sqlx::Error::RowNotFound) => {
-            return Ok(());
+    let selected_cached_time: DateTime<Local> = if let Some(snapshot_id) = snapshot {
+        match store
+            .snapshot_cached_date(dir_prop_row_id, snapshot_id)
+            .await?
+        {
+            Some(cached_date) => cached_date,
+            None => bail!("No snapshot {} found for {:?}", snapshot_id, dir),
+        }
+    } else if let Some(at) = at {
+        let at = parse_local_datetime(&at)?;
+        match store
+            .snapshot_at(dir_prop_row_id, LogActionType::CacheDates, at)
+            .await?
+        {
+            Some(cached_date) => cached_date,
+            None => bail!("No snapshot at or before {} found for {:?}", at, dir),
+        }
+    } else {
+        match store
+            .latest_snapshot(dir_prop_row_id, LogActionType::CacheDates)
+            .await?
+        {
+            Some(cached_date) => cached_date,
+            None => return Ok(()),
         }
-        Err(e) => return Err(e.into()),
     };
 
-    let files_props: Vec<_> = match sqlx::query_as::<_, (String, DateTime<Local>, DateTime<Local>)>(
-        "
-            SELECT name, created_date, modified_date FROM dir_file_props
-            WHERE dir_id = $1 AND cached_date = $2
-        ",
-    )
-    .bind(dir_prop_row_id)
-    .bind(latest_cached_time)
-    .fetch_all(&mut *conn)
-    .await
-    {
-        Ok(files_props) => files_props,
-        Err(sqlx::Error::RowNotFound) => return Ok(()),
-        Err(e) => return Err(e.into()),
-    };
+    let files_props = store
+        .files_for_snapshot(dir_prop_row_id, selected_cached_time)
+        .await?;
+
+    // First pass: match every record whose file is still at the name it was
+    // saved under.
+    let mut matched_paths: Vec<Option<PathBuf>> = vec![None; files_props.len()];
+    let mut matched_names = HashSet::new();
+    for (i, (name, _, _, _)) in files_props.iter().enumerate() {
+        let file_path = dir.join(name);
+        if is_file(&file_path).await {
+            matched_paths[i] = Some(file_path);
+            matched_names.insert(name.clone());
+        }
+    }
 
-    for (name, _, file_previous_modified_date) in files_props {
-        let file_path = dir.clone().join(name);
-        if !file_path.exists() || !file_path.is_file() {
+    // Second pass: for records that weren't matched by name (the file was
+    // renamed), fall back to matching by content hash against files that
+    // weren't already claimed above. Hashes are computed up front on a
+    // bounded pool of blocking workers rather than per-candidate.
+    let mut unclaimed_files = Vec::new();
+    let mut read_dir = tokio::fs::read_dir(&dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let path = entry.path();
+        if !is_file(&path).await {
             continue;
         }
-
-        let file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(file_path)?;
-        file.set_modified(datetime_local_to_system_time(file_previous_modified_date))?;
+        let already_matched = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| matched_names.contains(name))
+            .unwrap_or(false);
+        if !already_matched {
+            unclaimed_files.push(path);
+        }
     }
+    let unclaimed_hashes = hash_files(unclaimed_files).await;
 
-    tx.commit().await?;
+    let mut claimed_paths = HashSet::new();
+    for (i, (_, _, _, content_hash)) in files_props.iter().enumerate() {
+        if matched_paths[i].is_some() {
+            continue;
+        }
+        let Some(content_hash) = content_hash else {
+            continue;
+        };
 
-    Ok(())
-}
+        let matched_path = unclaimed_hashes
+            .iter()
+            .find(|(path, hash)| hash == content_hash && !claimed_paths.contains(*path))
+            .map(|(path, _)| path.clone());
 
-async fn save_dirs_props(pool: &SqlitePool, dir: PathBuf) -> Result<()> {
-    if !dir.exists() {
-        bail!("Doesn't exist dir: {:?}", dir);
+        if let Some(path) = matched_path {
+            claimed_paths.insert(path.clone());
+            matched_paths[i] = Some(path);
+        }
     }
-    let dir_prop_row_id: i64 = get_dir_prop_row_id(pool, dir.clone())
-        .await
-        .context(format!("Failed to get dir_prop_row_id: {:?}", dir))?;
-    let cached_time: DateTime<Local> = Local::now();
-    println!("save: {} | {:?}", dir_prop_row_id, dir);
-
-    let mut tx = pool.begin().await?;
-    let conn = tx.acquire().await?;
-
-    // 変更ログを残す
-    sqlx::query(
-        "
-            INSERT INTO dir_actions_log 
-            (dir_id, action_type, cached_date) 
-            VALUES ($1, $2, $3)
-        ",
-    )
-    .bind(dir_prop_row_id)
-    .bind(LogActionType::CacheDates)
-    .bind(cached_time)
-    .execute(&mut *conn)
-    .await?;
 
-    // ファイル毎にメタ情報を保存する
-    for entry in dir.read_dir()?.flatten() {
-        if !entry.file_type()?.is_file() {
+    let mut unchanged_count = 0;
+    let mut changed_count = 0;
+    let mut missing_count = 0;
+    let mut failed_count = 0;
+    let mut restored_count = 0;
+
+    for (i, (name, _, modified_date, _)) in files_props.iter().enumerate() {
+        let Some(path) = &matched_paths[i] else {
+            missing_count += 1;
+            if dry_run {
+                println!("missing       {name}");
+            }
+            continue;
+        };
+
+        if !dry_run {
+            match set_modified_date(path, *modified_date).await {
+                Ok(()) => restored_count += 1,
+                Err(e) => {
+                    failed_count += 1;
+                    eprintln!("failed to restore {:?}: {e}", path);
+                }
+            }
             continue;
         }
-        let file_name = entry.file_name();
-        let file_name = file_name.to_str().ok_or(anyhow!(
-            "Unable to convert OsString to &str: {:?}",
-            file_name
-        ))?;
-
-        if let Ok(metadata) = entry.metadata() {
-            let created_time: DateTime<Local> = system_time_to_datetime_local(metadata.created()?);
-            let modified_time: DateTime<Local> =
-                system_time_to_datetime_local(metadata.modified()?);
-
-            match sqlx::query_as::<_, (u32,)>(
-                "
-                    SELECT id FROM dir_file_props
-                    WHERE dir_id = $1 AND name = $2
-                    LIMIT 1
-                ",
-            )
-            .bind(dir_prop_row_id)
-            .bind(file_name)
-            .fetch_one(&mut *conn)
+
+        let current_modified = tokio::fs::metadata(path)
             .await
-            {
-                Ok((file_prop_row_id,)) => {
-                    sqlx::query(
-                        "
-                            UPDATE dir_file_props 
-                            SET cached_date = $2, created_date = $3, modified_date = $4
-                            WHERE id = $1
-                        ",
-                    )
-                    .bind(file_prop_row_id)
-                    .bind(cached_time)
-                    .bind(created_time)
-                    .bind(modified_time)
-                    .execute(&mut *conn)
-                    .await?;
-                }
-                Err(sqlx::Error::RowNotFound) => {
-                    sqlx::query(
-                        "
-                            INSERT INTO dir_file_props 
-                            (dir_id, name, cached_date, created_date, modified_date) 
-                            VALUES ($1, $2, $3, $4, $5)
-                        ",
-                    )
-                    .bind(dir_prop_row_id)
-                    .bind(file_name)
-                    .bind(cached_time)
-                    .bind(created_time)
-                    .bind(modified_time)
-                    .execute(&mut *conn)
-                    .await?;
-                }
-                Err(e) => return Err(e.into()),
-            };
+            .and_then(|metadata| metadata.modified())
+            .map(system_time_to_datetime_local)
+            .ok();
+
+        match current_modified {
+            Some(current) if current == *modified_date => {
+                unchanged_count += 1;
+                println!("unchanged     {name}");
+            }
+            Some(current) => {
+                changed_count += 1;
+                println!("would change  {name}  {current} -> {modified_date}");
+            }
+            None => {
+                changed_count += 1;
+                println!("would change  {name}  (unreadable) -> {modified_date}");
+            }
         }
     }
 
-    tx.commit().await?;
+    if dry_run {
+        println!(
+            "{unchanged_count} unchanged, {changed_count} would change, {missing_count} missing"
+        );
+    } else {
+        store
+            .record_apply(
+                dir_prop_row_id,
+                Local::now(),
+                selected_cached_time,
+                restored_count,
+            )
+            .await?;
+        if failed_count > 0 {
+            println!("{failed_count} file(s) failed to restore");
+        }
+    }
 
     Ok(())
 }
 
-async fn get_dir_prop_row_id(pool: &SqlitePool, dir: PathBuf) -> Result<i64> {
-    let mut dir_path_str = dir.clone();
-    let dir_path_str = dir_path_str
-        .as_mut_os_str()
-        .to_str()
-        .ok_or(anyhow!("Unable to convert PathBuf to &str: {:?}", dir))?;
-
-    let mut tx = pool.begin().await?;
-    let conn = tx.acquire().await?;
-
-    // poolのdir_propsテーブルから、dir_path_strに対応するprimary keyを取得する。存在しないなら新たに作成する。
-    let dir_prop_row_id: i64 = match sqlx::query_as::<_, (i64,)>(
-        "
-            SELECT id FROM dir_props
-            WHERE path = $1
-            LIMIT 1
-        ",
-    )
-    .bind(dir_path_str)
-    .fetch_one(&mut *conn)
-    .await
-    {
-        Ok((id,)) => id,
-        Err(sqlx::Error::RowNotFound) => {
-            let path_inserting_result = sqlx::query(
-                "
-                    INSERT INTO dir_props 
-                    (path) 
-                    VALUES ($1)
-                ",
-            )
-            .bind(dir_path_str)
-            .execute(&mut *conn)
-            .await?;
+async fn dir_exists(dir: &Path) -> bool {
+    tokio::fs::try_exists(dir).await.unwrap_or(false)
+}
 
-            path_inserting_result.last_insert_rowid()
-        }
-        Err(e) => return Err(e.into()),
-    };
+async fn is_file(path: &Path) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .map(|metadata| metadata.is_file())
+        .unwrap_or(false)
+}
 
-    tx.commit().await?;
+async fn list_dir_snapshots(store: &impl Store, dir: PathBuf) -> Result<()> {
+    if !dir_exists(&dir).await {
+        bail!("Doesn't exist dir: {:?}", dir);
+    }
+    let dir_prop_row_id = store
+        .resolve_dir_id(&dir)
+        .await
+        .context(format!("Failed to get dir_prop_row_id: {:?}", dir))?;
+
+    let snapshots = store
+        .list_snapshots(dir_prop_row_id, LogActionType::CacheDates)
+        .await?;
 
-    Ok(dir_prop_row_id)
+    if snapshots.is_empty() {
+        println!("No snapshots found for {:?}", dir);
+        return Ok(());
+    }
+
+    for snapshot in snapshots {
+        println!(
+            "{}\t{}\t{} file(s)",
+            snapshot.id, snapshot.cached_date, snapshot.file_count
+        );
+    }
+
+    Ok(())
 }
 
-#[derive(Debug, Clone, Copy)]
-enum LogActionType {
-    CacheDates,
+/// Sets a file's modified time off the async runtime, since `set_modified`
+/// has no `tokio::fs` equivalent.
+async fn set_modified_date(path: &Path, modified_date: DateTime<Local>) -> Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(&path)?;
+        file.set_modified(datetime_local_to_system_time(modified_date))?;
+        anyhow::Ok(())
+    })
+    .await??;
+
+    Ok(())
 }
 
-impl<'q> sqlx::Encode<'q, sqlx::Sqlite> for LogActionType {
-    fn encode_by_ref(
-        &self,
-        buf: &mut <sqlx::Sqlite as sqlx::Database>::ArgumentBuffer<'q>,
-    ) -> std::result::Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
-        let action_type: u8 = (*self).into();
-        buf.push(sqlx::sqlite::SqliteArgumentValue::Int(action_type as _));
+/// Hashes a file's contents for rename-resilient matching, returning `None`
+/// (rather than failing the whole directory) if the file can't be opened.
+fn hash_file(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finalize().to_hex().to_string())
+}
 
-        Ok(sqlx::encode::IsNull::No)
-    }
+/// Hashes every path in `paths` on a bounded pool of blocking workers,
+/// skipping (rather than failing on) any file that can't be opened.
+async fn hash_files(paths: Vec<PathBuf>) -> Vec<(PathBuf, String)> {
+    stream::iter(paths)
+        .map(|path| tokio::task::spawn_blocking(move || (path.clone(), hash_file(&path))))
+        .buffer_unordered(SCAN_WORKER_COUNT)
+        .filter_map(|result| async move {
+            let (path, hash) = result.ok()?;
+            hash.map(|hash| (path, hash))
+        })
+        .collect()
+        .await
 }
 
-impl sqlx::Type<sqlx::Sqlite> for LogActionType {
-    fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
-        u8::type_info()
+async fn save_dirs_props(store: &impl Store, dir: PathBuf, recursive: bool) -> Result<()> {
+    if !dir_exists(&dir).await {
+        bail!("Doesn't exist dir: {:?}", dir);
     }
-}
 
-impl From<u8> for LogActionType {
-    fn from(value: u8) -> Self {
-        match value {
-            0 => LogActionType::CacheDates,
-            _ => unreachable!(),
-        }
+    let max_depth = if recursive { usize::MAX } else { 1 };
+    let walk_root = dir.clone();
+    let file_paths: Vec<PathBuf> = tokio::task::spawn_blocking(move || {
+        WalkDir::new(&walk_root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect()
+    })
+    .await?;
+
+    // Collect metadata (and content hashes) for every file concurrently,
+    // spreading the blocking I/O across a bounded worker pool.
+    let snapshots_by_dir = collect_file_snapshots(file_paths).await;
+
+    let cached_time: DateTime<Local> = Local::now();
+    for (sub_dir, files) in snapshots_by_dir {
+        let dir_prop_row_id = store
+            .resolve_dir_id(&sub_dir)
+            .await
+            .context(format!("Failed to get dir_prop_row_id: {:?}", sub_dir))?;
+        println!("save: {} | {:?}", dir_prop_row_id, sub_dir);
+
+        store
+            .record_snapshot(dir_prop_row_id, LogActionType::CacheDates, cached_time)
+            .await?;
+        store
+            .upsert_file_props(dir_prop_row_id, cached_time, files)
+            .await?;
     }
+
+    Ok(())
 }
-impl From<LogActionType> for u8 {
-    fn from(value: LogActionType) -> Self {
-        match value {
-            LogActionType::CacheDates => 0,
-        }
+
+/// Stats and hashes every path in `file_paths` on a bounded pool of blocking
+/// workers, grouping the resulting snapshots by parent directory so each
+/// directory's files can be persisted in a single batched upsert. A file that
+/// can't be read is logged and skipped rather than failing the whole scan; a
+/// file whose created time isn't supported by the filesystem is kept, with its
+/// modified date recorded as the created date.
+async fn collect_file_snapshots(file_paths: Vec<PathBuf>) -> HashMap<PathBuf, Vec<FileSnapshot>> {
+    let snapshots: Vec<(PathBuf, FileSnapshot)> = stream::iter(file_paths)
+        .map(|path| {
+            tokio::task::spawn_blocking(move || {
+                let result = build_file_snapshot(&path);
+                (path, result)
+            })
+        })
+        .buffer_unordered(SCAN_WORKER_COUNT)
+        .filter_map(|joined| async move {
+            let (path, result) = joined.ok()?;
+            match result {
+                Ok(snapshot) => Some((path, snapshot)),
+                Err(reason) => {
+                    eprintln!("skipping {:?}: {reason}", path);
+                    None
+                }
+            }
+        })
+        .collect()
+        .await;
+
+    let mut by_dir: HashMap<PathBuf, Vec<FileSnapshot>> = HashMap::new();
+    for (path, snapshot) in snapshots {
+        let parent = path.parent().unwrap_or(&path).to_path_buf();
+        by_dir.entry(parent).or_default().push(snapshot);
     }
+    by_dir
 }
 
-fn system_time_to_datetime_local(system_time: SystemTime) -> DateTime<Local> {
-    system_time.into()
+fn build_file_snapshot(path: &Path) -> Result<FileSnapshot, String> {
+    let metadata = path
+        .metadata()
+        .map_err(|e| format!("unreadable file: {e}"))?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| "file name is not valid UTF-8".to_string())?
+        .to_string();
+    let modified_date = metadata
+        .modified()
+        .map(system_time_to_datetime_local)
+        .map_err(|e| format!("modified time unreadable: {e}"))?;
+    // Some filesystems don't support creation time at all; fall back to the
+    // modified date rather than dropping the whole file, since that's the
+    // scenario this field's caller is meant to handle gracefully.
+    let created_date = metadata
+        .created()
+        .map(system_time_to_datetime_local)
+        .unwrap_or(modified_date);
+    let content_hash = hash_file(path);
+
+    Ok(FileSnapshot {
+        name,
+        created_date,
+        modified_date,
+        content_hash,
+    })
 }
 
-fn datetime_local_to_system_time(datetime: DateTime<Local>) -> SystemTime {
-    datetime.into()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memory_store::InMemoryStore;
+    use std::time::{Duration, SystemTime};
+
+    fn unique_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "retaining_date_test_{label}_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn set_mtime(path: &Path, when: SystemTime) {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .unwrap();
+        file.set_modified(when).unwrap();
+    }
+
+    #[tokio::test]
+    async fn apply_matches_a_renamed_file_by_content_hash() {
+        let dir = unique_temp_dir("rename_match");
+        let original_path = dir.join("original.txt");
+        std::fs::write(&original_path, b"same bytes").unwrap();
+        let saved_mtime = std::fs::metadata(&original_path).unwrap().modified().unwrap();
+
+        let store = InMemoryStore::new();
+        save_dirs_props(&store, dir.clone(), false).await.unwrap();
+
+        let renamed_path = dir.join("renamed.txt");
+        std::fs::rename(&original_path, &renamed_path).unwrap();
+        let drifted_mtime = SystemTime::now() - Duration::from_secs(3600);
+        set_mtime(&renamed_path, drifted_mtime);
+
+        apply_dirs_props(&store, dir.clone(), None, None, false)
+            .await
+            .unwrap();
+
+        let restored = std::fs::metadata(&renamed_path).unwrap().modified().unwrap();
+        let drift = restored
+            .duration_since(saved_mtime)
+            .unwrap_or_else(|e| e.duration());
+        assert!(drift < Duration::from_secs(2), "drift was {drift:?}");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn apply_snapshot_restores_an_older_snapshot_not_the_latest() {
+        let dir = unique_temp_dir("point_in_time");
+        let file_path = dir.join("file.txt");
+        std::fs::write(&file_path, b"content").unwrap();
+
+        let store = InMemoryStore::new();
+        let dir_id = store.resolve_dir_id(&dir).await.unwrap();
+
+        let older_modified: DateTime<Local> =
+            system_time_to_datetime_local(SystemTime::now() - Duration::from_secs(48 * 3600));
+        let older_cached: DateTime<Local> =
+            system_time_to_datetime_local(SystemTime::now() - Duration::from_secs(2 * 3600));
+        store
+            .record_snapshot(dir_id, LogActionType::CacheDates, older_cached)
+            .await
+            .unwrap();
+        store
+            .upsert_file_props(
+                dir_id,
+                older_cached,
+                vec![FileSnapshot {
+                    name: "file.txt".to_string(),
+                    created_date: older_modified,
+                    modified_date: older_modified,
+                    content_hash: None,
+                }],
+            )
+            .await
+            .unwrap();
+
+        let newer_modified: DateTime<Local> = system_time_to_datetime_local(SystemTime::now());
+        let newer_cached: DateTime<Local> = newer_modified;
+        store
+            .record_snapshot(dir_id, LogActionType::CacheDates, newer_cached)
+            .await
+            .unwrap();
+        store
+            .upsert_file_props(
+                dir_id,
+                newer_cached,
+                vec![FileSnapshot {
+                    name: "file.txt".to_string(),
+                    created_date: newer_modified,
+                    modified_date: newer_modified,
+                    content_hash: None,
+                }],
+            )
+            .await
+            .unwrap();
+
+        let snapshots = store
+            .list_snapshots(dir_id, LogActionType::CacheDates)
+            .await
+            .unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots.iter().all(|s| s.file_count == 1));
+        let older_snapshot_id = snapshots
+            .iter()
+            .find(|s| s.cached_date == older_cached)
+            .unwrap()
+            .id;
+
+        apply_dirs_props(&store, dir.clone(), None, Some(older_snapshot_id), false)
+            .await
+            .unwrap();
+
+        let restored = std::fs::metadata(&file_path).unwrap().modified().unwrap();
+        let expected = datetime_local_to_system_time(older_modified);
+        let drift = restored
+            .duration_since(expected)
+            .unwrap_or_else(|e| e.duration());
+        assert!(drift < Duration::from_secs(2), "drift was {drift:?}");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_without_touching_any_file() {
+        let dir = unique_temp_dir("dry_run");
+        let file_path = dir.join("file.txt");
+        std::fs::write(&file_path, b"content").unwrap();
+
+        let store = InMemoryStore::new();
+        save_dirs_props(&store, dir.clone(), false).await.unwrap();
+
+        let drifted_mtime = SystemTime::now() - Duration::from_secs(600);
+        set_mtime(&file_path, drifted_mtime);
+
+        apply_dirs_props(&store, dir.clone(), None, None, true)
+            .await
+            .unwrap();
+
+        let after_dry_run = std::fs::metadata(&file_path).unwrap().modified().unwrap();
+        assert_eq!(after_dry_run, drifted_mtime);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -0,0 +1,220 @@
+//! An in-memory `Store` used only by tests, so `main.rs`'s restore/matching
+//! logic can be exercised without a SQLite database.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+
+use crate::store::{FileSnapshot, SnapshotInfo, Store};
+use crate::types::LogActionType;
+
+#[derive(Debug, Clone)]
+struct FileRow {
+    dir_id: i64,
+    name: String,
+    cached_date: DateTime<Local>,
+    created_date: DateTime<Local>,
+    modified_date: DateTime<Local>,
+    content_hash: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct LogRow {
+    id: i64,
+    dir_id: i64,
+    action_type: u8,
+    cached_date: DateTime<Local>,
+}
+
+#[derive(Default)]
+struct Inner {
+    dirs: Vec<(PathBuf, i64)>,
+    logs: Vec<LogRow>,
+    files: Vec<FileRow>,
+}
+
+#[derive(Default)]
+pub struct InMemoryStore {
+    inner: Mutex<Inner>,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Store for InMemoryStore {
+    async fn resolve_dir_id(&self, dir: &Path) -> Result<i64> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some((_, id)) = inner.dirs.iter().find(|(path, _)| path == dir) {
+            return Ok(*id);
+        }
+        let id = inner.dirs.len() as i64 + 1;
+        inner.dirs.push((dir.to_path_buf(), id));
+        Ok(id)
+    }
+
+    async fn record_snapshot(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+        cached_date: DateTime<Local>,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.logs.len() as i64 + 1;
+        inner.logs.push(LogRow {
+            id,
+            dir_id,
+            action_type: action.into(),
+            cached_date,
+        });
+        Ok(())
+    }
+
+    async fn upsert_file_props(
+        &self,
+        dir_id: i64,
+        cached_date: DateTime<Local>,
+        files: Vec<FileSnapshot>,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        for file in files {
+            let existing = inner.files.iter_mut().find(|row| {
+                row.dir_id == dir_id && row.name == file.name && row.cached_date == cached_date
+            });
+            match existing {
+                Some(row) => {
+                    row.created_date = file.created_date;
+                    row.modified_date = file.modified_date;
+                    row.content_hash = file.content_hash;
+                }
+                None => inner.files.push(FileRow {
+                    dir_id,
+                    name: file.name,
+                    cached_date,
+                    created_date: file.created_date,
+                    modified_date: file.modified_date,
+                    content_hash: file.content_hash,
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    async fn latest_snapshot(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+    ) -> Result<Option<DateTime<Local>>> {
+        let inner = self.inner.lock().unwrap();
+        let action: u8 = action.into();
+        Ok(inner
+            .logs
+            .iter()
+            .filter(|log| log.dir_id == dir_id && log.action_type == action)
+            .map(|log| log.cached_date)
+            .max())
+    }
+
+    async fn snapshot_at(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+        at: DateTime<Local>,
+    ) -> Result<Option<DateTime<Local>>> {
+        let inner = self.inner.lock().unwrap();
+        let action: u8 = action.into();
+        Ok(inner
+            .logs
+            .iter()
+            .filter(|log| log.dir_id == dir_id && log.action_type == action && log.cached_date <= at)
+            .map(|log| log.cached_date)
+            .max())
+    }
+
+    async fn snapshot_cached_date(
+        &self,
+        dir_id: i64,
+        snapshot_id: i64,
+    ) -> Result<Option<DateTime<Local>>> {
+        let inner = self.inner.lock().unwrap();
+        let cache_dates: u8 = LogActionType::CacheDates.into();
+        Ok(inner
+            .logs
+            .iter()
+            .find(|log| log.id == snapshot_id && log.dir_id == dir_id && log.action_type == cache_dates)
+            .map(|log| log.cached_date))
+    }
+
+    async fn list_snapshots(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+    ) -> Result<Vec<SnapshotInfo>> {
+        let inner = self.inner.lock().unwrap();
+        let action: u8 = action.into();
+        let mut snapshots: Vec<SnapshotInfo> = inner
+            .logs
+            .iter()
+            .filter(|log| log.dir_id == dir_id && log.action_type == action)
+            .map(|log| {
+                let file_count = inner
+                    .files
+                    .iter()
+                    .filter(|row| row.dir_id == dir_id && row.cached_date == log.cached_date)
+                    .count() as i64;
+                SnapshotInfo {
+                    id: log.id,
+                    cached_date: log.cached_date,
+                    file_count,
+                }
+            })
+            .collect();
+        snapshots.sort_by(|a, b| b.cached_date.cmp(&a.cached_date));
+        Ok(snapshots)
+    }
+
+    async fn files_for_snapshot(
+        &self,
+        dir_id: i64,
+        cached_date: DateTime<Local>,
+    ) -> Result<Vec<(String, DateTime<Local>, DateTime<Local>, Option<String>)>> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .files
+            .iter()
+            .filter(|row| row.dir_id == dir_id && row.cached_date == cached_date)
+            .map(|row| {
+                (
+                    row.name.clone(),
+                    row.created_date,
+                    row.modified_date,
+                    row.content_hash.clone(),
+                )
+            })
+            .collect())
+    }
+
+    async fn record_apply(
+        &self,
+        dir_id: i64,
+        applied_at: DateTime<Local>,
+        _source_cached_date: DateTime<Local>,
+        _restored_count: i64,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.logs.len() as i64 + 1;
+        inner.logs.push(LogRow {
+            id,
+            dir_id,
+            action_type: LogActionType::ApplyDates.into(),
+            cached_date: applied_at,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use sqlx::{Acquire, SqlitePool};
+
+use crate::store::{FileSnapshot, SnapshotInfo, Store};
+use crate::types::LogActionType;
+
+/// `Store` implementation backed by the crate's SQLite schema
+/// (`dir_props`, `dir_actions_log`, `dir_file_props`).
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn resolve_dir_id(&self, dir: &Path) -> Result<i64> {
+        let dir_path_str = dir
+            .to_str()
+            .ok_or_else(|| anyhow!("Unable to convert PathBuf to &str: {:?}", dir))?;
+
+        let mut tx = self.pool.begin().await?;
+        let conn = tx.acquire().await?;
+
+        let dir_prop_row_id: i64 = match sqlx::query_as::<_, (i64,)>(
+            "
+                SELECT id FROM dir_props
+                WHERE path = $1
+                LIMIT 1
+            ",
+        )
+        .bind(dir_path_str)
+        .fetch_one(&mut *conn)
+        .await
+        {
+            Ok((id,)) => id,
+            Err(sqlx::Error::RowNotFound) => {
+                let path_inserting_result = sqlx::query(
+                    "
+                        INSERT INTO dir_props
+                        (path)
+                        VALUES ($1)
+                    ",
+                )
+                .bind(dir_path_str)
+                .execute(&mut *conn)
+                .await?;
+
+                path_inserting_result.last_insert_rowid()
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        tx.commit().await?;
+
+        Ok(dir_prop_row_id)
+    }
+
+    async fn record_snapshot(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+        cached_date: DateTime<Local>,
+    ) -> Result<()> {
+        // 変更ログを残す
+        sqlx::query(
+            "
+                INSERT INTO dir_actions_log
+                (dir_id, action_type, cached_date)
+                VALUES ($1, $2, $3)
+            ",
+        )
+        .bind(dir_id)
+        .bind(action)
+        .bind(cached_date)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn upsert_file_props(
+        &self,
+        dir_id: i64,
+        cached_date: DateTime<Local>,
+        files: Vec<FileSnapshot>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let conn = tx.acquire().await?;
+
+        for file in files {
+            sqlx::query(
+                "
+                    INSERT INTO dir_file_props
+                    (dir_id, name, cached_date, created_date, modified_date, content_hash)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    ON CONFLICT(dir_id, name, cached_date) DO UPDATE SET
+                        created_date = excluded.created_date,
+                        modified_date = excluded.modified_date,
+                        content_hash = excluded.content_hash
+                ",
+            )
+            .bind(dir_id)
+            .bind(file.name)
+            .bind(cached_date)
+            .bind(file.created_date)
+            .bind(file.modified_date)
+            .bind(file.content_hash)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn latest_snapshot(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+    ) -> Result<Option<DateTime<Local>>> {
+        match sqlx::query_as::<_, (DateTime<Local>,)>(
+            "
+                SELECT cached_date FROM dir_actions_log
+                WHERE dir_id = $1 AND action_type = $2
+                ORDER BY cached_date DESC
+                LIMIT 1
+            ",
+        )
+        .bind(dir_id)
+        .bind(action)
+        .fetch_one(&self.pool)
+        .await
+        {
+            Ok((cached_date,)) => Ok(Some(cached_date)),
+            Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn snapshot_at(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+        at: DateTime<Local>,
+    ) -> Result<Option<DateTime<Local>>> {
+        match sqlx::query_as::<_, (DateTime<Local>,)>(
+            "
+                SELECT cached_date FROM dir_actions_log
+                WHERE dir_id = $1 AND action_type = $2 AND cached_date <= $3
+                ORDER BY cached_date DESC
+                LIMIT 1
+            ",
+        )
+        .bind(dir_id)
+        .bind(action)
+        .bind(at)
+        .fetch_one(&self.pool)
+        .await
+        {
+            Ok((cached_date,)) => Ok(Some(cached_date)),
+            Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn snapshot_cached_date(
+        &self,
+        dir_id: i64,
+        snapshot_id: i64,
+    ) -> Result<Option<DateTime<Local>>> {
+        match sqlx::query_as::<_, (DateTime<Local>,)>(
+            "
+                SELECT cached_date FROM dir_actions_log
+                WHERE id = $1 AND dir_id = $2 AND action_type = $3
+                LIMIT 1
+            ",
+        )
+        .bind(snapshot_id)
+        .bind(dir_id)
+        .bind(LogActionType::CacheDates)
+        .fetch_one(&self.pool)
+        .await
+        {
+            Ok((cached_date,)) => Ok(Some(cached_date)),
+            Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list_snapshots(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+    ) -> Result<Vec<SnapshotInfo>> {
+        let snapshots = sqlx::query_as::<_, (i64, DateTime<Local>, i64)>(
+            "
+                SELECT l.id, l.cached_date, (
+                    SELECT COUNT(*) FROM dir_file_props f
+                    WHERE f.dir_id = l.dir_id AND f.cached_date = l.cached_date
+                )
+                FROM dir_actions_log l
+                WHERE l.dir_id = $1 AND l.action_type = $2
+                ORDER BY l.cached_date DESC
+            ",
+        )
+        .bind(dir_id)
+        .bind(action)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|(id, cached_date, file_count)| SnapshotInfo {
+            id,
+            cached_date,
+            file_count,
+        })
+        .collect();
+
+        Ok(snapshots)
+    }
+
+    async fn files_for_snapshot(
+        &self,
+        dir_id: i64,
+        cached_date: DateTime<Local>,
+    ) -> Result<Vec<(String, DateTime<Local>, DateTime<Local>, Option<String>)>> {
+        let files_props = sqlx::query_as::<_, (String, DateTime<Local>, DateTime<Local>, Option<String>)>(
+            "
+                SELECT name, created_date, modified_date, content_hash FROM dir_file_props
+                WHERE dir_id = $1 AND cached_date = $2
+            ",
+        )
+        .bind(dir_id)
+        .bind(cached_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(files_props)
+    }
+
+    async fn record_apply(
+        &self,
+        dir_id: i64,
+        applied_at: DateTime<Local>,
+        source_cached_date: DateTime<Local>,
+        restored_count: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "
+                INSERT INTO dir_actions_log
+                (dir_id, action_type, cached_date, source_cached_date, restored_count)
+                VALUES ($1, $2, $3, $4, $5)
+            ",
+        )
+        .bind(dir_id)
+        .bind(LogActionType::ApplyDates)
+        .bind(applied_at)
+        .bind(source_cached_date)
+        .bind(restored_count)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl<'q> sqlx::Encode<'q, sqlx::Sqlite> for LogActionType {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <sqlx::Sqlite as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> std::result::Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        let action_type: u8 = (*self).into();
+        buf.push(sqlx::sqlite::SqliteArgumentValue::Int(action_type as _));
+
+        Ok(sqlx::encode::IsNull::No)
+    }
+}
+
+impl sqlx::Type<sqlx::Sqlite> for LogActionType {
+    fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+        u8::type_info()
+    }
+}
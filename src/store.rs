@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+
+use crate::types::LogActionType;
+
+/// A single file's captured metadata, ready to be persisted as part of a
+/// directory's snapshot. `content_hash` is `None` when the file couldn't be
+/// read while hashing, in which case later restores fall back to matching on
+/// `name` alone.
+#[derive(Debug, Clone)]
+pub struct FileSnapshot {
+    pub name: String,
+    pub created_date: DateTime<Local>,
+    pub modified_date: DateTime<Local>,
+    pub content_hash: Option<String>,
+}
+
+/// A single entry in a directory's snapshot history, as surfaced by the
+/// `List` command.
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    pub id: i64,
+    pub cached_date: DateTime<Local>,
+    pub file_count: i64,
+}
+
+/// Abstracts the persistence layer used by the CLI's directory-snapshot
+/// commands, so `main.rs` can stay free of raw SQL and be exercised against
+/// an in-memory implementation in tests.
+#[async_trait]
+pub trait Store {
+    /// Returns the row id for `dir`, creating it if this is the first time
+    /// the directory has been seen.
+    async fn resolve_dir_id(&self, dir: &Path) -> Result<i64>;
+
+    /// Records that a snapshot of `action` was taken for `dir_id` at
+    /// `cached_date`.
+    async fn record_snapshot(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+        cached_date: DateTime<Local>,
+    ) -> Result<()>;
+
+    /// Upserts `files` for `dir_id`'s `cached_date` snapshot in a single
+    /// transaction, keyed on `(dir_id, name, cached_date)` so each snapshot
+    /// keeps its own rows instead of overwriting an earlier one.
+    async fn upsert_file_props(
+        &self,
+        dir_id: i64,
+        cached_date: DateTime<Local>,
+        files: Vec<FileSnapshot>,
+    ) -> Result<()>;
+
+    /// Returns the timestamp of the most recent snapshot of `action` for
+    /// `dir_id`, or `None` if no snapshot has ever been taken.
+    async fn latest_snapshot(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+    ) -> Result<Option<DateTime<Local>>>;
+
+    /// Returns the timestamp of the most recent snapshot of `action` for
+    /// `dir_id` taken at or before `at`, or `None` if there isn't one.
+    async fn snapshot_at(
+        &self,
+        dir_id: i64,
+        action: LogActionType,
+        at: DateTime<Local>,
+    ) -> Result<Option<DateTime<Local>>>;
+
+    /// Resolves a snapshot selected by its `dir_actions_log` row id to its
+    /// `cached_date`, as long as it belongs to `dir_id` and is a
+    /// `CacheDates` row (not, say, an `ApplyDates` log entry).
+    async fn snapshot_cached_date(
+        &self,
+        dir_id: i64,
+        snapshot_id: i64,
+    ) -> Result<Option<DateTime<Local>>>;
+
+    /// Lists every snapshot of `action` recorded for `dir_id`, most recent
+    /// first, alongside how many files each one captured.
+    async fn list_snapshots(&self, dir_id: i64, action: LogActionType)
+        -> Result<Vec<SnapshotInfo>>;
+
+    /// Returns the `(name, created_date, modified_date, content_hash)` rows
+    /// saved for `dir_id` as of `cached_date`.
+    async fn files_for_snapshot(
+        &self,
+        dir_id: i64,
+        cached_date: DateTime<Local>,
+    ) -> Result<Vec<(String, DateTime<Local>, DateTime<Local>, Option<String>)>>;
+
+    /// Records that the snapshot taken at `source_cached_date` was applied
+    /// to `dir_id` at `applied_at`, restoring `restored_count` files.
+    async fn record_apply(
+        &self,
+        dir_id: i64,
+        applied_at: DateTime<Local>,
+        source_cached_date: DateTime<Local>,
+        restored_count: i64,
+    ) -> Result<()>;
+}
@@ -0,0 +1,58 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDateTime};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy)]
+pub enum LogActionType {
+    CacheDates,
+    ApplyDates,
+    /// A value read back from `dir_actions_log` that this build doesn't
+    /// recognize yet, preserved rather than rejected so older logs stay
+    /// readable after a rollback.
+    Unknown(u8),
+}
+
+impl From<u8> for LogActionType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => LogActionType::CacheDates,
+            1 => LogActionType::ApplyDates,
+            other => LogActionType::Unknown(other),
+        }
+    }
+}
+impl From<LogActionType> for u8 {
+    fn from(value: LogActionType) -> Self {
+        match value {
+            LogActionType::CacheDates => 0,
+            LogActionType::ApplyDates => 1,
+            LogActionType::Unknown(other) => other,
+        }
+    }
+}
+
+pub fn system_time_to_datetime_local(system_time: SystemTime) -> DateTime<Local> {
+    system_time.into()
+}
+
+pub fn datetime_local_to_system_time(datetime: DateTime<Local>) -> SystemTime {
+    datetime.into()
+}
+
+/// Parses a user-supplied `--at <datetime>` value (e.g. `2024-01-02
+/// 15:04:05`) as a local time.
+pub fn parse_local_datetime(input: &str) -> Result<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M:%S"))
+        .map_err(|_| {
+            anyhow!(
+                "Invalid datetime {:?}, expected a format like '2024-01-02 15:04:05'",
+                input
+            )
+        })?;
+
+    naive
+        .and_local_timezone(Local)
+        .single()
+        .ok_or_else(|| anyhow!("Ambiguous or invalid local datetime: {:?}", input))
+}